@@ -1,84 +1,19 @@
+mod engine;
+
+use engine::{ChessBoard, Color as PieceColor, Piece, PieceType};
 use ggez::{Context, ContextBuilder, GameResult};
-use ggez::event::{self, EventHandler};
+use ggez::event::{self, EventHandler, KeyCode, KeyMods};
 use ggez::graphics::{self, Color, DrawParam, Image, Rect};
 use ggez::input::mouse::MouseButton;
 use std::path;
 
-#[derive(Copy, Clone, PartialEq)]
-enum PieceType {
-    King, Queen, Rook, Bishop, Knight, Pawn,
-}
-
-#[derive(Copy, Clone, PartialEq)]
-enum PieceColor {
-    White,
-    Black,
-}
-
-#[derive(Copy, Clone)]
-struct Piece {
-    piece_type: PieceType,
-    color: PieceColor,
-}
-
-struct ChessBoard {
-    board: [[Option<Piece>; 8]; 8],
-    current_turn: PieceColor,
-}
-
-impl ChessBoard {
-    fn new() -> ChessBoard {
-        let mut board = [[None; 8]; 8];
-
-        // Initialize pawns
-        for i in 0..8 {
-            board[1][i] = Some(Piece { piece_type: PieceType::Pawn, color: PieceColor::White });
-            board[6][i] = Some(Piece { piece_type: PieceType::Pawn, color: PieceColor::Black });
-        }
-
-        // Initialize other pieces
-        let piece_order = [
-            PieceType::Rook, PieceType::Knight, PieceType::Bishop, PieceType::Queen,
-            PieceType::King, PieceType::Bishop, PieceType::Knight, PieceType::Rook,
-        ];
-
-        for (i, &piece_type) in piece_order.iter().enumerate() {
-            board[0][i] = Some(Piece { piece_type, color: PieceColor::White });
-            board[7][i] = Some(Piece { piece_type, color: PieceColor::Black });
-        }
-
-        ChessBoard {
-            board,
-            current_turn: PieceColor::White,
-        }
-    }
-
-    fn is_valid_move(&self, _from: (usize, usize), _to: (usize, usize)) -> bool {
-        true // Placeholder logic
-    }
-
-
-    fn make_move(&mut self, from: (usize, usize), to: (usize, usize)) -> bool {
-        if !self.is_valid_move(from, to) {
-            return false;
-        }
-
-        self.board[to.0][to.1] = self.board[from.0][from.1];
-        self.board[from.0][from.1] = None;
-        self.current_turn = if self.current_turn == PieceColor::White {
-            PieceColor::Black
-        } else {
-            PieceColor::White
-        };
-        true
-    }
-}
-
 struct MainState {
     chess_board: ChessBoard,
     piece_images: std::collections::HashMap<String, Image>,
     selected_square: Option<(usize, usize)>,
     square_size: f32,
+    // Piece a pawn promotes to when it reaches the back rank; pick with Q/R/B/N.
+    promotion_choice: PieceType,
 }
 
 impl MainState {
@@ -103,9 +38,22 @@ impl MainState {
             piece_images,
             selected_square: None,
             square_size,
+            promotion_choice: PieceType::Queen,
         })
     }
 
+    // The promotion piece to pass to `make_move` if `from -> to` is a pawn
+    // reaching the back rank, `None` otherwise.
+    fn pending_promotion(&self, from: (usize, usize), to: (usize, usize)) -> Option<PieceType> {
+        match self.chess_board.at(from) {
+            Some(Piece { piece_type: PieceType::Pawn, color }) => {
+                let back_rank = if color == PieceColor::White { 7 } else { 0 };
+                if to.0 == back_rank { Some(self.promotion_choice) } else { None }
+            }
+            _ => None,
+        }
+    }
+
     fn get_square_from_coords(&self, x: f32, y: f32) -> Option<(usize, usize)> {
         let file = (x / self.square_size) as usize;
         let rank = 7 - (y / self.square_size) as usize;
@@ -126,6 +74,16 @@ impl EventHandler for MainState {
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         graphics::clear(ctx, Color::from_rgb(40, 40, 40));
 
+        // Legal destinations for the selected piece, highlighted below.
+        let legal_targets: Vec<(usize, usize)> = match self.selected_square {
+            Some(from) => self.chess_board.legal_moves()
+                .into_iter()
+                .filter(|&(move_from, _, _)| move_from == from)
+                .map(|(_, to, _)| to)
+                .collect(),
+            None => Vec::new(),
+        };
+
         // Draw chessboard and pieces
         for rank in 0..8 {
             for file in 0..8 {
@@ -163,8 +121,24 @@ impl EventHandler for MainState {
                     }
                 }
 
+                // Highlight legal destinations for the selected piece
+                if legal_targets.contains(&(rank, file)) {
+                    let marker = graphics::Mesh::new_circle(
+                        ctx,
+                        graphics::DrawMode::fill(),
+                        [
+                            (file as f32 + 0.5) * self.square_size,
+                            (7 - rank) as f32 * self.square_size + 0.5 * self.square_size,
+                        ],
+                        self.square_size * 0.12,
+                        0.5,
+                        Color::from_rgba(20, 20, 20, 160),
+                    )?;
+                    graphics::draw(ctx, &marker, DrawParam::default())?;
+                }
+
                 // Draw pieces
-                if let Some(piece) = self.chess_board.board[rank][file] {
+                if let Some(piece) = self.chess_board.at((rank, file)) {
                     let piece_name = match (piece.color, piece.piece_type) {
                         (PieceColor::White, PieceType::King) => "wk",
                         (PieceColor::White, PieceType::Queen) => "wq",
@@ -215,16 +189,17 @@ impl EventHandler for MainState {
                 match self.selected_square {
                     None => {
                         // Select piece
-                        if self.chess_board.board[square.0][square.1].is_some() {
+                        if self.chess_board.at(square).is_some() {
                             self.selected_square = Some(square);
                         }
                     }
                     Some(from) => {
                         // Move piece
-                        if self.chess_board.make_move(from, square) {
+                        let promotion = self.pending_promotion(from, square);
+                        if self.chess_board.make_move(from, square, promotion) {
                             self.selected_square = None;
                         } else {
-                            if self.chess_board.board[square.0][square.1].is_some() {
+                            if self.chess_board.at(square).is_some() {
                                 self.selected_square = Some(square);
                             } else {
                                 self.selected_square = None;
@@ -235,6 +210,22 @@ impl EventHandler for MainState {
             }
         }
     }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        keycode: KeyCode,
+        _keymods: KeyMods,
+        _repeat: bool,
+    ) {
+        self.promotion_choice = match keycode {
+            KeyCode::Q => PieceType::Queen,
+            KeyCode::R => PieceType::Rook,
+            KeyCode::B => PieceType::Bishop,
+            KeyCode::N => PieceType::Knight,
+            _ => self.promotion_choice,
+        };
+    }
 }
 
 fn main() -> GameResult {
@@ -1,5 +1,6 @@
 use std::fmt;
 use std::io;
+use std::sync::OnceLock;
 
 #[derive(Copy, Clone, PartialEq)]
 enum PieceType {
@@ -23,19 +24,223 @@ struct Piece {
     color: Color,
 }
 
+// Castling-rights index for the "kingside"/"queenside" slot of a color's
+// `[bool; 2]` entry.
+const KINGSIDE: usize = 0;
+const QUEENSIDE: usize = 1;
+
+fn color_index(color: Color) -> usize {
+    if color == Color::White { 0 } else { 1 }
+}
+
+// `pieces` bitboard slot for each piece type, in the order `PieceType` is declared.
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+fn piece_type_index(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::King => 0,
+        PieceType::Queen => 1,
+        PieceType::Rook => 2,
+        PieceType::Bishop => 3,
+        PieceType::Knight => 4,
+        PieceType::Pawn => 5,
+    }
+}
+
+// `(rank, file)` <-> bit index `rank * 8 + file` in the `colors`/`pieces` bitboards.
+fn square_index(square: (usize, usize)) -> usize {
+    square.0 * 8 + square.1
+}
+
+fn square_from_index(index: usize) -> (usize, usize) {
+    (index / 8, index % 8)
+}
+
+// The extra board changes a move can trigger beyond "piece leaves `from`,
+// lands on `to`", kept separate from the geometry checks in `is_valid_move`.
+#[derive(Copy, Clone, PartialEq)]
+enum MoveSideEffect {
+    None,
+    Capture,
+    EnPassant { captured_square: (usize, usize) },
+    Castle { rook_from: (usize, usize), rook_to: (usize, usize) },
+}
+
+// SplitMix64, seeded with a fixed constant so the Zobrist tables below come
+// out the same on every run.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+// Random keys for incremental Zobrist hashing: one per (color, piece type,
+// square), one for the side to move, one per castling-rights slot (ordered
+// to match `color_index(..) * 2 + {KINGSIDE, QUEENSIDE}`), and one per
+// en passant file.
+struct ZobristKeys {
+    piece: [[[u64; 64]; 6]; 2],
+    side_to_move: u64,
+    castle: [u64; 4],
+    en_passant_file: [u64; 8],
+}
+
+impl ZobristKeys {
+    fn new() -> ZobristKeys {
+        let mut rng = SplitMix64::new(0x9E3779B97F4A7C15);
+
+        let mut piece = [[[0u64; 64]; 6]; 2];
+        for color in piece.iter_mut() {
+            for piece_type in color.iter_mut() {
+                for square in piece_type.iter_mut() {
+                    *square = rng.next();
+                }
+            }
+        }
+
+        let side_to_move = rng.next();
+
+        let mut castle = [0u64; 4];
+        for key in castle.iter_mut() {
+            *key = rng.next();
+        }
+
+        let mut en_passant_file = [0u64; 8];
+        for key in en_passant_file.iter_mut() {
+            *key = rng.next();
+        }
+
+        ZobristKeys { piece, side_to_move, castle, en_passant_file }
+    }
+}
+
+fn zobrist_keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(ZobristKeys::new)
+}
+
+// XORs `piece`'s key at `square` into both the position hash and, when it's a
+// pawn, the separate pawn-structure hash.
+fn toggle_piece(hash: &mut u64, pawn_hash: &mut u64, piece: Piece, square: (usize, usize)) {
+    let key = zobrist_keys().piece[color_index(piece.color)][piece_type_index(piece.piece_type)][square_index(square)];
+    *hash ^= key;
+    if piece.piece_type == PieceType::Pawn {
+        *pawn_hash ^= key;
+    }
+}
+
+// One bitboard per color plus one per piece type, bit `rank * 8 + file`, as in
+// the `chess`/Vatu crates' bitboard representation.
+#[derive(Clone)]
 struct ChessBoard {
-    board: [[Option<Piece>; 8]; 8],
+    colors: [u64; 2],
+    pieces: [u64; 6],
     current_turn: Color,
+    castling_rights: [[bool; 2]; 2],
+    en_passant: Option<(usize, usize)>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+    pawn_hash: u64,
+    history: Vec<u64>,
+}
+
+#[derive(Debug)]
+enum FenError {
+    WrongFieldCount(usize),
+    BadPlacement(String),
+    BadActiveColor(String),
+    BadCastling(String),
+    BadEnPassant(String),
+    BadCounter(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(n) => write!(f, "expected 6 space-separated FEN fields, got {}", n),
+            FenError::BadPlacement(s) => write!(f, "invalid piece placement field: {}", s),
+            FenError::BadActiveColor(s) => write!(f, "invalid active color field: {}", s),
+            FenError::BadCastling(s) => write!(f, "invalid castling availability field: {}", s),
+            FenError::BadEnPassant(s) => write!(f, "invalid en passant target field: {}", s),
+            FenError::BadCounter(s) => write!(f, "invalid halfmove/fullmove counter: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+// Letter used in both the FEN piece placement field and the CLI `Display`
+// board, following FEN's "uppercase white, lowercase black" convention.
+fn piece_letter(piece: Piece) -> char {
+    let letter = match piece.piece_type {
+        PieceType::King => 'k',
+        PieceType::Queen => 'q',
+        PieceType::Rook => 'r',
+        PieceType::Bishop => 'b',
+        PieceType::Knight => 'n',
+        PieceType::Pawn => 'p',
+    };
+    if piece.color == Color::White { letter.to_ascii_uppercase() } else { letter }
+}
+
+fn piece_from_letter(letter: char) -> Option<Piece> {
+    let piece_type = match letter.to_ascii_lowercase() {
+        'k' => PieceType::King,
+        'q' => PieceType::Queen,
+        'r' => PieceType::Rook,
+        'b' => PieceType::Bishop,
+        'n' => PieceType::Knight,
+        'p' => PieceType::Pawn,
+        _ => return None,
+    };
+    let color = if letter.is_ascii_uppercase() { Color::White } else { Color::Black };
+    Some(Piece { piece_type, color })
+}
+
+// Algebraic square name, e.g. `(2, 4)` -> "e3".
+fn square_to_algebraic(square: (usize, usize)) -> String {
+    format!("{}{}", (b'a' + square.1 as u8) as char, square.0 + 1)
 }
 
 impl ChessBoard {
     fn new() -> ChessBoard {
-        let mut board = [[None; 8]; 8];
+        let mut board = ChessBoard {
+            colors: [0; 2],
+            pieces: [0; 6],
+            current_turn: Color::White,
+            castling_rights: [[true, true], [true, true]],
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            pawn_hash: 0,
+            history: Vec::new(),
+        };
 
         // Initialize pawns
         for i in 0..8 {
-            board[1][i] = Some(Piece { piece_type: PieceType::Pawn, color: Color::White });
-            board[6][i] = Some(Piece { piece_type: PieceType::Pawn, color: Color::Black });
+            board.set((1, i), Piece { piece_type: PieceType::Pawn, color: Color::White });
+            board.set((6, i), Piece { piece_type: PieceType::Pawn, color: Color::Black });
         }
 
         // Initialize other pieces
@@ -51,13 +256,100 @@ impl ChessBoard {
         ];
 
         for (i, &piece_type) in piece_order.iter().enumerate() {
-            board[0][i] = Some(Piece { piece_type, color: Color::White });
-            board[7][i] = Some(Piece { piece_type, color: Color::Black });
+            board.set((0, i), Piece { piece_type, color: Color::White });
+            board.set((7, i), Piece { piece_type, color: Color::Black });
         }
 
-        ChessBoard {
-            board,
-            current_turn: Color::White,
+        let (hash, pawn_hash) = board.compute_hash();
+        board.hash = hash;
+        board.pawn_hash = pawn_hash;
+        board.history.push(hash);
+        board
+    }
+
+    // Computes the Zobrist hash (and separate pawn-structure hash) for the
+    // position from scratch. Used at construction; `make_move` maintains
+    // `hash`/`pawn_hash` incrementally afterwards.
+    fn compute_hash(&self) -> (u64, u64) {
+        let keys = zobrist_keys();
+        let mut hash = 0u64;
+        let mut pawn_hash = 0u64;
+
+        for index in 0..64 {
+            let square = square_from_index(index);
+            if let Some(piece) = self.at(square) {
+                toggle_piece(&mut hash, &mut pawn_hash, piece, square);
+            }
+        }
+
+        if self.current_turn == Color::Black {
+            hash ^= keys.side_to_move;
+        }
+        for color in 0..2 {
+            for side in 0..2 {
+                if self.castling_rights[color][side] {
+                    hash ^= keys.castle[color * 2 + side];
+                }
+            }
+        }
+        if let Some(square) = self.en_passant {
+            hash ^= keys.en_passant_file[square.1];
+        }
+
+        (hash, pawn_hash)
+    }
+
+    fn combined(&self) -> u64 {
+        self.colors[0] | self.colors[1]
+    }
+
+    fn is_empty(&self, square: (usize, usize)) -> bool {
+        self.combined() & (1u64 << square_index(square)) == 0
+    }
+
+    fn color_at(&self, square: (usize, usize)) -> Option<Color> {
+        let bit = 1u64 << square_index(square);
+        if self.colors[color_index(Color::White)] & bit != 0 {
+            Some(Color::White)
+        } else if self.colors[color_index(Color::Black)] & bit != 0 {
+            Some(Color::Black)
+        } else {
+            None
+        }
+    }
+
+    fn at(&self, square: (usize, usize)) -> Option<Piece> {
+        let color = self.color_at(square)?;
+        let bit = 1u64 << square_index(square);
+        for &piece_type in PIECE_TYPES.iter() {
+            if self.pieces[piece_type_index(piece_type)] & bit != 0 {
+                return Some(Piece { piece_type, color });
+            }
+        }
+        None
+    }
+
+    fn set(&mut self, square: (usize, usize), piece: Piece) {
+        let bit = 1u64 << square_index(square);
+        self.colors[color_index(piece.color)] |= bit;
+        self.pieces[piece_type_index(piece.piece_type)] |= bit;
+    }
+
+    fn clear(&mut self, square: (usize, usize)) {
+        let mask = !(1u64 << square_index(square));
+        self.colors[0] &= mask;
+        self.colors[1] &= mask;
+        for piece_bits in self.pieces.iter_mut() {
+            *piece_bits &= mask;
+        }
+    }
+
+    // Moves whatever sits on `from` to `to`, clearing any piece captured there.
+    fn move_piece(&mut self, from: (usize, usize), to: (usize, usize)) {
+        if let Some(piece) = self.at(from) {
+            self.clear(to);
+            self.clear(from);
+            self.set(to, piece);
         }
     }
 
@@ -66,7 +358,7 @@ impl ChessBoard {
             return false;
         }
 
-        let piece = match self.board[from.0][from.1] {
+        let piece = match self.at(from) {
             Some(p) => p,
             None => return false,
         };
@@ -76,12 +368,19 @@ impl ChessBoard {
         }
 
         // Check if destination contains a piece of the same color
-        if let Some(dest_piece) = self.board[to.0][to.1] {
+        if let Some(dest_piece) = self.at(to) {
             if dest_piece.color == piece.color {
                 return false;
             }
         }
 
+        if piece.piece_type == PieceType::King
+            && from.0 == to.0
+            && (to.1 as i32 - from.1 as i32).abs() == 2
+        {
+            return self.is_valid_castle(from, to, piece.color);
+        }
+
         match piece.piece_type {
             PieceType::Pawn => self.is_valid_pawn_move(from, to, piece.color),
             PieceType::Rook => self.is_valid_rook_move(from, to),
@@ -92,6 +391,41 @@ impl ChessBoard {
         }
     }
 
+    // King and chosen rook unmoved, the squares between them empty, and the
+    // king neither starts in, passes through, nor lands on an attacked square.
+    fn is_valid_castle(&self, from: (usize, usize), to: (usize, usize), color: Color) -> bool {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        if from != (back_rank, 4) {
+            return false;
+        }
+
+        let (side, rook_col, transit_cols) = if to.1 == 6 {
+            (KINGSIDE, 7, [4, 5, 6])
+        } else if to.1 == 2 {
+            (QUEENSIDE, 0, [4, 3, 2])
+        } else {
+            return false;
+        };
+
+        if !self.castling_rights[color_index(color)][side] {
+            return false;
+        }
+
+        match self.at((back_rank, rook_col)) {
+            Some(p) if p.color == color && p.piece_type == PieceType::Rook => {}
+            _ => return false,
+        }
+
+        if !self.path_is_clear((back_rank, 4), (back_rank, rook_col)) {
+            return false;
+        }
+
+        let opponent = if color == Color::White { Color::Black } else { Color::White };
+        !transit_cols
+            .iter()
+            .any(|&col| self.is_square_attacked((back_rank, col), opponent))
+    }
+
     fn is_valid_pawn_move(&self, from: (usize, usize), to: (usize, usize), color: Color) -> bool {
         let direction = if color == Color::White { 1 } else { -1 };
         let start_row = if color == Color::White { 1 } else { 6 };
@@ -100,25 +434,30 @@ impl ChessBoard {
         let double_forward = (from.0 as i32 + 2 * direction) as usize;
 
         // Normal move forward
-        if to.0 == forward && to.1 == from.1 && self.board[to.0][to.1].is_none() {
+        if to.0 == forward && to.1 == from.1 && self.is_empty(to) {
             return true;
         }
 
         // Initial double move
         if from.0 == start_row && to.0 == double_forward && to.1 == from.1 {
-            return self.board[forward][from.1].is_none() && self.board[to.0][to.1].is_none();
+            return self.is_empty((forward, from.1)) && self.is_empty(to);
         }
 
         // Capture
         if to.0 == forward && (to.1 as i32 - from.1 as i32).abs() == 1 {
-            return self.board[to.0][to.1].is_some();
+            if !self.is_empty(to) {
+                return true;
+            }
+            // En passant: capture a just-double-moved enemy pawn on the
+            // square it skipped over.
+            return self.en_passant == Some(to);
         }
 
         false
     }
 
     fn is_valid_rook_move(&self, from: (usize, usize), to: (usize, usize)) -> bool {
-        from.0 == to.0 || from.1 == to.1
+        (from.0 == to.0 || from.1 == to.1) && self.path_is_clear(from, to)
     }
 
     fn is_valid_knight_move(&self, from: (usize, usize), to: (usize, usize)) -> bool {
@@ -130,33 +469,468 @@ impl ChessBoard {
     fn is_valid_bishop_move(&self, from: (usize, usize), to: (usize, usize)) -> bool {
         let dx = (to.0 as i32 - from.0 as i32).abs();
         let dy = (to.1 as i32 - from.1 as i32).abs();
-        dx == dy
+        dx == dy && self.path_is_clear(from, to)
     }
 
     fn is_valid_queen_move(&self, from: (usize, usize), to: (usize, usize)) -> bool {
         self.is_valid_rook_move(from, to) || self.is_valid_bishop_move(from, to)
     }
 
+    // Walks the single-step direction from `from` to `to`, not including either
+    // endpoint, and returns false if any square in between is occupied.
+    fn path_is_clear(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let row_step = (to.0 as i32 - from.0 as i32).signum();
+        let col_step = (to.1 as i32 - from.1 as i32).signum();
+
+        let mut row = from.0 as i32 + row_step;
+        let mut col = from.1 as i32 + col_step;
+
+        while (row, col) != (to.0 as i32, to.1 as i32) {
+            if !self.is_empty((row as usize, col as usize)) {
+                return false;
+            }
+            row += row_step;
+            col += col_step;
+        }
+
+        true
+    }
+
     fn is_valid_king_move(&self, from: (usize, usize), to: (usize, usize)) -> bool {
         let dx = (to.0 as i32 - from.0 as i32).abs();
         let dy = (to.1 as i32 - from.1 as i32).abs();
         dx <= 1 && dy <= 1
     }
 
-    fn make_move(&mut self, from: (usize, usize), to: (usize, usize)) -> bool {
+    fn find_king(&self, color: Color) -> Option<(usize, usize)> {
+        let king_bits = self.pieces[piece_type_index(PieceType::King)] & self.colors[color_index(color)];
+        if king_bits == 0 {
+            None
+        } else {
+            Some(square_from_index(king_bits.trailing_zeros() as usize))
+        }
+    }
+
+    // Checks whether `sq` is attacked by a piece of color `by`, reusing the
+    // per-piece geometry (ignoring whose turn it actually is). Walks the
+    // attacker's occupancy bitboard instead of scanning the full 8x8 array.
+    fn is_square_attacked(&self, sq: (usize, usize), by: Color) -> bool {
+        let mut attackers = self.colors[color_index(by)];
+        while attackers != 0 {
+            let from_index = attackers.trailing_zeros() as usize;
+            attackers &= attackers - 1;
+            let from = square_from_index(from_index);
+            let piece = self.at(from).expect("occupancy bit implies a piece is present");
+
+            let attacks = match piece.piece_type {
+                PieceType::Pawn => {
+                    let direction = if by == Color::White { 1 } else { -1 };
+                    let attack_row = (from.0 as i32 + direction) as usize;
+                    attack_row == sq.0 && (sq.1 as i32 - from.1 as i32).abs() == 1
+                }
+                PieceType::Rook => self.is_valid_rook_move(from, sq),
+                PieceType::Knight => self.is_valid_knight_move(from, sq),
+                PieceType::Bishop => self.is_valid_bishop_move(from, sq),
+                PieceType::Queen => self.is_valid_queen_move(from, sq),
+                PieceType::King => self.is_valid_king_move(from, sq),
+            };
+
+            if attacks {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn is_in_check(&self, color: Color) -> bool {
+        let opponent = if color == Color::White { Color::Black } else { Color::White };
+        match self.find_king(color) {
+            Some(king_square) => self.is_square_attacked(king_square, opponent),
+            None => false,
+        }
+    }
+
+    // Classifies what, beyond "piece leaves `from`, lands on `to`", this move
+    // triggers. Assumes `from`/`to` already passed `is_valid_move`.
+    fn side_effect_for(&self, from: (usize, usize), to: (usize, usize)) -> MoveSideEffect {
+        let piece = self.at(from).expect("validated move always has a piece");
+
+        if piece.piece_type == PieceType::Pawn && from.1 != to.1 && self.is_empty(to) {
+            return MoveSideEffect::EnPassant { captured_square: (from.0, to.1) };
+        }
+
+        if piece.piece_type == PieceType::King && (to.1 as i32 - from.1 as i32).abs() == 2 {
+            let back_rank = from.0;
+            return if to.1 == 6 {
+                MoveSideEffect::Castle { rook_from: (back_rank, 7), rook_to: (back_rank, 5) }
+            } else {
+                MoveSideEffect::Castle { rook_from: (back_rank, 0), rook_to: (back_rank, 3) }
+            };
+        }
+
+        if !self.is_empty(to) {
+            MoveSideEffect::Capture
+        } else {
+            MoveSideEffect::None
+        }
+    }
+
+    // Applies `from -> to` plus its side effects and any pawn promotion to a
+    // copy of the board, without touching turn or castling/en-passant state.
+    fn apply_move(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        promotion: Option<PieceType>,
+    ) -> ChessBoard {
+        let mut next = self.clone();
+        let piece = self.at(from).expect("validated move always has a piece");
+
+        match self.side_effect_for(from, to) {
+            MoveSideEffect::EnPassant { captured_square } => next.clear(captured_square),
+            MoveSideEffect::Castle { rook_from, rook_to } => next.move_piece(rook_from, rook_to),
+            MoveSideEffect::Capture | MoveSideEffect::None => {}
+        }
+
+        next.move_piece(from, to);
+
+        let promotion_rank = if piece.color == Color::White { 7 } else { 0 };
+        if piece.piece_type == PieceType::Pawn && to.0 == promotion_rank {
+            next.clear(to);
+            next.set(to, Piece { piece_type: promotion.unwrap_or(PieceType::Queen), color: piece.color });
+        }
+
+        next
+    }
+
+    // Geometry validity plus the rule that a move may not leave the mover's
+    // own king in check.
+    fn is_legal_move(&self, from: (usize, usize), to: (usize, usize)) -> bool {
         if !self.is_valid_move(from, to) {
             return false;
         }
 
-        self.board[to.0][to.1] = self.board[from.0][from.1];
-        self.board[from.0][from.1] = None;
-        self.current_turn = if self.current_turn == Color::White {
-            Color::Black
+        let mover = match self.at(from) {
+            Some(p) => p.color,
+            None => return false,
+        };
+
+        let after_move = self.apply_move(from, to, None);
+
+        !after_move.is_in_check(mover)
+    }
+
+    // Applies castling, en passant, and promotion as first-class,
+    // all-or-nothing side effects of the move (`promotion` defaults to Queen).
+    fn make_move(&mut self, from: (usize, usize), to: (usize, usize), promotion: Option<PieceType>) -> bool {
+        if !self.is_legal_move(from, to) {
+            return false;
+        }
+
+        let piece = self.at(from).expect("validated move always has a piece");
+        let opponent = if piece.color == Color::White { Color::Black } else { Color::White };
+        let side_effect = self.side_effect_for(from, to);
+        let is_capture = matches!(side_effect, MoveSideEffect::Capture | MoveSideEffect::EnPassant { .. });
+
+        let mut hash = self.hash;
+        let mut pawn_hash = self.pawn_hash;
+        let old_castling_rights = self.castling_rights;
+        let old_en_passant = self.en_passant;
+
+        match side_effect {
+            MoveSideEffect::EnPassant { captured_square } => {
+                let captured = self.at(captured_square).expect("en passant target holds a pawn");
+                toggle_piece(&mut hash, &mut pawn_hash, captured, captured_square);
+            }
+            MoveSideEffect::Castle { rook_from, rook_to } => {
+                let rook = self.at(rook_from).expect("castling rook is present");
+                toggle_piece(&mut hash, &mut pawn_hash, rook, rook_from);
+                toggle_piece(&mut hash, &mut pawn_hash, rook, rook_to);
+            }
+            MoveSideEffect::Capture => {
+                let captured = self.at(to).expect("capture target holds a piece");
+                toggle_piece(&mut hash, &mut pawn_hash, captured, to);
+            }
+            MoveSideEffect::None => {}
+        }
+
+        toggle_piece(&mut hash, &mut pawn_hash, piece, from);
+        let promotion_rank = if piece.color == Color::White { 7 } else { 0 };
+        let landing_piece = if piece.piece_type == PieceType::Pawn && to.0 == promotion_rank {
+            Piece { piece_type: promotion.unwrap_or(PieceType::Queen), color: piece.color }
         } else {
-            Color::White
+            piece
         };
+        toggle_piece(&mut hash, &mut pawn_hash, landing_piece, to);
+        hash ^= zobrist_keys().side_to_move;
+
+        let after_move = self.apply_move(from, to, promotion);
+        self.colors = after_move.colors;
+        self.pieces = after_move.pieces;
+
+        if piece.piece_type == PieceType::Pawn || is_capture {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if piece.color == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.en_passant = if piece.piece_type == PieceType::Pawn
+            && (to.0 as i32 - from.0 as i32).abs() == 2
+        {
+            Some(((from.0 + to.0) / 2, from.1))
+        } else {
+            None
+        };
+
+        if piece.piece_type == PieceType::King {
+            self.castling_rights[color_index(piece.color)] = [false, false];
+        }
+        if piece.piece_type == PieceType::Rook {
+            let back_rank = if piece.color == Color::White { 0 } else { 7 };
+            if from == (back_rank, 0) {
+                self.castling_rights[color_index(piece.color)][QUEENSIDE] = false;
+            } else if from == (back_rank, 7) {
+                self.castling_rights[color_index(piece.color)][KINGSIDE] = false;
+            }
+        }
+        // A rook captured on its home square can no longer be castled with.
+        let opponent_back_rank = if piece.color == Color::White { 7 } else { 0 };
+        if to == (opponent_back_rank, 0) {
+            self.castling_rights[color_index(opponent)][QUEENSIDE] = false;
+        } else if to == (opponent_back_rank, 7) {
+            self.castling_rights[color_index(opponent)][KINGSIDE] = false;
+        }
+
+        let keys = zobrist_keys();
+        if let Some(square) = old_en_passant {
+            hash ^= keys.en_passant_file[square.1];
+        }
+        if let Some(square) = self.en_passant {
+            hash ^= keys.en_passant_file[square.1];
+        }
+        for color in 0..2 {
+            for side in 0..2 {
+                if old_castling_rights[color][side] != self.castling_rights[color][side] {
+                    hash ^= keys.castle[color * 2 + side];
+                }
+            }
+        }
+
+        self.current_turn = opponent;
+        self.hash = hash;
+        self.pawn_hash = pawn_hash;
+        self.history.push(hash);
         true
     }
+
+    // True once the current position's hash has occurred three times in this
+    // game's history (threefold repetition).
+    fn is_threefold_repetition(&self) -> bool {
+        self.history.iter().filter(|&&h| h == self.hash).count() >= 3
+    }
+
+    // Every move the side to move can legally make: for each friendly piece,
+    // every geometrically valid destination that doesn't leave its own king
+    // in check, with promotions expanded into one entry per promotion piece.
+    fn legal_moves(&self) -> Vec<((usize, usize), (usize, usize), Option<PieceType>)> {
+        let mut moves = Vec::new();
+
+        for from_rank in 0..8 {
+            for from_file in 0..8 {
+                let from = (from_rank, from_file);
+                let piece = match self.at(from) {
+                    Some(p) if p.color == self.current_turn => p,
+                    _ => continue,
+                };
+
+                let promotion_rank = if piece.color == Color::White { 7 } else { 0 };
+
+                for to_rank in 0..8 {
+                    for to_file in 0..8 {
+                        let to = (to_rank, to_file);
+                        if from == to || !self.is_legal_move(from, to) {
+                            continue;
+                        }
+
+                        if piece.piece_type == PieceType::Pawn && to.0 == promotion_rank {
+                            for &promotion in &[
+                                PieceType::Queen,
+                                PieceType::Rook,
+                                PieceType::Bishop,
+                                PieceType::Knight,
+                            ] {
+                                moves.push((from, to, Some(promotion)));
+                            }
+                        } else {
+                            moves.push((from, to, None));
+                        }
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    // Standard correctness/benchmark routine: the number of legal move
+    // sequences of length `depth` from this position.
+    fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let mut nodes = 0;
+        for (from, to, promotion) in self.legal_moves() {
+            let mut next = self.clone();
+            next.make_move(from, to, promotion);
+            nodes += next.perft(depth - 1);
+        }
+        nodes
+    }
+
+    // Serializes the position to Forsyth-Edwards Notation: piece placement,
+    // active color, castling availability, en passant target, and the
+    // halfmove/fullmove counters.
+    fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..8).rev() {
+            let mut empty_run = 0;
+            for file in 0..8 {
+                match self.at((rank, file)) {
+                    Some(piece) => {
+                        if empty_run > 0 {
+                            placement.push_str(&empty_run.to_string());
+                            empty_run = 0;
+                        }
+                        placement.push(piece_letter(piece));
+                    }
+                    None => empty_run += 1,
+                }
+            }
+            if empty_run > 0 {
+                placement.push_str(&empty_run.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let active_color = if self.current_turn == Color::White { "w" } else { "b" };
+
+        let mut castling = String::new();
+        if self.castling_rights[color_index(Color::White)][KINGSIDE] {
+            castling.push('K');
+        }
+        if self.castling_rights[color_index(Color::White)][QUEENSIDE] {
+            castling.push('Q');
+        }
+        if self.castling_rights[color_index(Color::Black)][KINGSIDE] {
+            castling.push('k');
+        }
+        if self.castling_rights[color_index(Color::Black)][QUEENSIDE] {
+            castling.push('q');
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = match self.en_passant {
+            Some(square) => square_to_algebraic(square),
+            None => "-".to_string(),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement, active_color, castling, en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+
+    // Parses Forsyth-Edwards Notation into a board, the inverse of `to_fen`.
+    fn from_fen(s: &str) -> Result<ChessBoard, FenError> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+        let (placement, active_color, castling, en_passant, halfmove, fullmove) =
+            (fields[0], fields[1], fields[2], fields[3], fields[4], fields[5]);
+
+        let mut colors = [0u64; 2];
+        let mut pieces = [0u64; 6];
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::BadPlacement(placement.to_string()));
+        }
+        for (rank_from_top, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - rank_from_top;
+            let mut file = 0;
+            for c in rank_str.chars() {
+                if file >= 8 {
+                    return Err(FenError::BadPlacement(placement.to_string()));
+                }
+                if let Some(skip) = c.to_digit(10) {
+                    file += skip as usize;
+                } else {
+                    let piece = piece_from_letter(c).ok_or_else(|| FenError::BadPlacement(placement.to_string()))?;
+                    let bit = 1u64 << square_index((rank, file));
+                    colors[color_index(piece.color)] |= bit;
+                    pieces[piece_type_index(piece.piece_type)] |= bit;
+                    file += 1;
+                }
+            }
+            if file != 8 {
+                return Err(FenError::BadPlacement(placement.to_string()));
+            }
+        }
+
+        let current_turn = match active_color {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::BadActiveColor(active_color.to_string())),
+        };
+
+        let mut castling_rights = [[false, false], [false, false]];
+        if castling != "-" {
+            for c in castling.chars() {
+                match c {
+                    'K' => castling_rights[color_index(Color::White)][KINGSIDE] = true,
+                    'Q' => castling_rights[color_index(Color::White)][QUEENSIDE] = true,
+                    'k' => castling_rights[color_index(Color::Black)][KINGSIDE] = true,
+                    'q' => castling_rights[color_index(Color::Black)][QUEENSIDE] = true,
+                    _ => return Err(FenError::BadCastling(castling.to_string())),
+                }
+            }
+        }
+
+        let en_passant = if en_passant == "-" {
+            None
+        } else {
+            Some(parse_position(en_passant).ok_or_else(|| FenError::BadEnPassant(en_passant.to_string()))?)
+        };
+
+        let halfmove_clock = halfmove.parse().map_err(|_| FenError::BadCounter(halfmove.to_string()))?;
+        let fullmove_number = fullmove.parse().map_err(|_| FenError::BadCounter(fullmove.to_string()))?;
+
+        let mut board = ChessBoard {
+            colors,
+            pieces,
+            current_turn,
+            castling_rights,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            hash: 0,
+            pawn_hash: 0,
+            history: Vec::new(),
+        };
+        let (hash, pawn_hash) = board.compute_hash();
+        board.hash = hash;
+        board.pawn_hash = pawn_hash;
+        board.history.push(hash);
+        Ok(board)
+    }
 }
 
 impl fmt::Display for ChessBoard {
@@ -165,24 +939,8 @@ impl fmt::Display for ChessBoard {
         for i in (0..8).rev() {
             write!(f, "{} ", i + 1)?;
             for j in 0..8 {
-                match self.board[i][j] {
-                    Some(piece) => {
-                        let symbol = match (piece.piece_type, piece.color) {
-                            (PieceType::King, Color::White) => "K",
-                            (PieceType::King, Color::Black) => "k",
-                            (PieceType::Queen, Color::White) => "Q",
-                            (PieceType::Queen, Color::Black) => "q",
-                            (PieceType::Rook, Color::White) => "R",
-                            (PieceType::Rook, Color::Black) => "r",
-                            (PieceType::Bishop, Color::White) => "B",
-                            (PieceType::Bishop, Color::Black) => "b",
-                            (PieceType::Knight, Color::White) => "N",
-                            (PieceType::Knight, Color::Black) => "n",
-                            (PieceType::Pawn, Color::White) => "P",
-                            (PieceType::Pawn, Color::Black) => "p",
-                        };
-                        write!(f, "{} ", symbol)?;
-                    }
+                match self.at((i, j)) {
+                    Some(piece) => write!(f, "{} ", piece_letter(piece))?,
                     None => write!(f, ". ")?,
                 }
             }
@@ -218,7 +976,7 @@ fn main() {
         println!("{}", board);
         println!("Current turn: {}",
                  if board.current_turn == Color::White { "White" } else { "Black" });
-        println!("Enter move (e.g., 'e2 e4') or 'quit' to exit:");
+        println!("Enter move (e.g., 'e2 e4'), 'fen' to print the current position, 'fen <string>' to load one, 'perft <depth>' to count legal move paths, or 'quit' to exit:");
 
         input.clear();
         io::stdin().read_line(&mut input).unwrap();
@@ -228,6 +986,27 @@ fn main() {
             break;
         }
 
+        if input == "fen" {
+            println!("{}", board.to_fen());
+            continue;
+        }
+
+        if let Some(fen) = input.strip_prefix("fen ") {
+            match ChessBoard::from_fen(fen) {
+                Ok(new_board) => board = new_board,
+                Err(e) => println!("Invalid FEN: {}", e),
+            }
+            continue;
+        }
+
+        if let Some(depth) = input.strip_prefix("perft ") {
+            match depth.parse::<u32>() {
+                Ok(depth) => println!("perft({}) = {}", depth, board.perft(depth)),
+                Err(_) => println!("Invalid depth"),
+            }
+            continue;
+        }
+
         let parts: Vec<&str> = input.split_whitespace().collect();
         if parts.len() != 2 {
             println!("Invalid input format. Use 'from to' notation (e.g., 'e2 e4')");
@@ -250,9 +1029,39 @@ fn main() {
             }
         };
 
-        if !board.make_move(from, to) {
+        if !board.make_move(from, to, None) {
             println!("Invalid move!");
             continue;
         }
+
+        if board.is_threefold_repetition() {
+            println!("Draw by threefold repetition!");
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known-good perft node counts from the start position (see
+    // https://www.chessprogramming.org/Perft_Results).
+    #[test]
+    fn perft_from_start_position() {
+        let board = ChessBoard::new();
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.perft(3), 8902);
+    }
+
+    #[test]
+    fn fen_round_trips() {
+        let start_fen = ChessBoard::new().to_fen();
+        assert_eq!(start_fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(ChessBoard::from_fen(&start_fen).unwrap().to_fen(), start_fen);
+
+        let midgame_fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        assert_eq!(ChessBoard::from_fen(midgame_fen).unwrap().to_fen(), midgame_fen);
     }
 }
\ No newline at end of file